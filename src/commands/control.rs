@@ -5,6 +5,7 @@ use std::fmt;
 
 use crate::{RiscvChip, WchLinkVariant};
 
+use super::probe::Probe;
 use super::*;
 
 /// GetDeviceVersion (0x0d, 0x01)
@@ -179,3 +180,195 @@ impl Command for SetPower {
         }
     }
 }
+
+/// GetReadProtect (0x0d, 0x06)
+#[derive(Debug)]
+pub struct GetReadProtect;
+impl Command for GetReadProtect {
+    type Response = ReadProtect;
+    const COMMAND_ID: u8 = 0x0d;
+    fn payload(&self) -> Vec<u8> {
+        vec![0x06]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadProtect {
+    Unprotected,
+    Protected,
+}
+impl Response for ReadProtect {
+    fn from_payload(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(0x00) => Ok(ReadProtect::Unprotected),
+            Some(0x01) => Ok(ReadProtect::Protected),
+            _ => Err(crate::error::Error::InvalidPayloadLength),
+        }
+    }
+}
+
+/// SetReadProtect (0x0d, 0x07)
+#[derive(Debug)]
+pub struct SetReadProtect(pub bool);
+impl Command for SetReadProtect {
+    type Response = ();
+    const COMMAND_ID: u8 = 0x0d;
+    fn payload(&self) -> Vec<u8> {
+        vec![0x07, self.0 as u8]
+    }
+}
+
+/// Enable or disable read-protection on the attached chip.
+///
+/// Clearing RDP mass-erases the flash, so disabling protection follows up
+/// with the same erase-plus-reset flow as [`EraseCodeFlash::ByPowerOff`];
+/// enabling it does not erase anything. `Ch549` doesn't support this
+/// command.
+pub fn set_read_protect<P: Probe>(
+    probe: &mut P,
+    chip: RiscvChip,
+    variant: WchLinkVariant,
+    enable: bool,
+) -> Result<()> {
+    if variant == WchLinkVariant::Ch549 {
+        return Err(crate::error::Error::UnsupportedVariant(variant));
+    }
+    probe.send_command(SetReadProtect(enable))?;
+    if !enable {
+        probe.send_command(EraseCodeFlash::ByPowerOff(chip))?;
+    }
+    Ok(())
+}
+
+/// GetTargetVoltage (0x0d, 0x0D)
+#[derive(Debug)]
+pub struct GetTargetVoltage;
+impl Command for GetTargetVoltage {
+    type Response = TargetVoltage;
+    const COMMAND_ID: u8 = 0x0d;
+    fn payload(&self) -> Vec<u8> {
+        vec![0x0D]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetVoltage {
+    pub millivolts: u32,
+}
+impl Response for TargetVoltage {
+    fn from_payload(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 4 {
+            return Err(crate::error::Error::InvalidPayloadLength);
+        }
+        Ok(Self {
+            millivolts: u32::from_be_bytes(bytes.try_into().unwrap()),
+        })
+    }
+}
+impl fmt::Display for TargetVoltage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:03} V",
+            self.millivolts / 1000,
+            self.millivolts % 1000
+        )
+    }
+}
+
+/// Oldest firmware version, as reported by [`ProbeInfo::version`], that
+/// implements [`GetTargetVoltage`].
+const MIN_VOLTAGE_VERSION: (u8, u8) = (2, 9);
+
+/// Read the measured target voltage, rejecting probes whose firmware
+/// predates voltage reporting.
+pub fn get_target_voltage<P: Probe>(probe: &mut P, info: &ProbeInfo) -> Result<TargetVoltage> {
+    if info.version() < MIN_VOLTAGE_VERSION {
+        return Err(crate::error::Error::UnsupportedFirmware {
+            required: MIN_VOLTAGE_VERSION,
+            actual: info.version(),
+        });
+    }
+    probe.send_command(GetTargetVoltage)
+}
+
+/// Disable the 3V3 rail, hold it off for `off_ms`, then re-enable it.
+///
+/// Some targets need a clean brown-out before attach (notably right after
+/// [`EraseCodeFlash::ByPowerOff`] clears RDP); sequencing the two
+/// `SetPower` calls with a deterministic delay here is more reliable than
+/// leaving the timing to the caller.
+pub fn power_cycle<P: Probe>(probe: &mut P, off_ms: u32) -> Result<()> {
+    probe.send_command(SetPower::Disable3V3)?;
+    std::thread::sleep(std::time::Duration::from_millis(off_ms as u64));
+    probe.send_command(SetPower::Enable3V3)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::probe::MockProbe;
+
+    #[test]
+    fn set_read_protect_disable_erases() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        probe.push_response(vec![]); // SetReadProtect
+        probe.push_response(vec![]); // EraseCodeFlash::ByPowerOff
+        set_read_protect(&mut probe, RiscvChip::CH32V203, variant, false).unwrap();
+        assert_eq!(probe.issued.len(), 2);
+    }
+
+    #[test]
+    fn set_read_protect_enable_does_not_erase() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        probe.push_response(vec![]); // SetReadProtect only
+        set_read_protect(&mut probe, RiscvChip::CH32V203, variant, true).unwrap();
+        assert_eq!(probe.issued.len(), 1);
+    }
+
+    #[test]
+    fn set_read_protect_rejects_unsupported_variant() {
+        let mut probe = MockProbe::new();
+        let err = set_read_protect(&mut probe, RiscvChip::CH32V203, WchLinkVariant::Ch549, true);
+        assert!(err.is_err());
+        assert!(probe.issued.is_empty());
+    }
+
+    #[test]
+    fn get_target_voltage_rejects_old_firmware() {
+        let mut probe = MockProbe::new();
+        let info = ProbeInfo {
+            major_version: 2,
+            minor_version: 8,
+            variant: WchLinkVariant::Ch549,
+        };
+        assert!(get_target_voltage(&mut probe, &info).is_err());
+        assert!(probe.issued.is_empty());
+    }
+
+    #[test]
+    fn get_target_voltage_reads_millivolts() {
+        let mut probe = MockProbe::new();
+        let info = ProbeInfo {
+            major_version: 2,
+            minor_version: 9,
+            variant: WchLinkVariant::Ch549,
+        };
+        probe.push_response(vec![0x00, 0x00, 0x0c, 0xe4]);
+        let voltage = get_target_voltage(&mut probe, &info).unwrap();
+        assert_eq!(voltage.millivolts, 3300);
+    }
+
+    #[test]
+    fn power_cycle_disables_then_enables_3v3() {
+        let mut probe = MockProbe::new();
+        probe.push_response(vec![]); // Disable3V3
+        probe.push_response(vec![]); // Enable3V3
+        power_cycle(&mut probe, 0).unwrap();
+        assert_eq!(probe.issued[0].1, vec![0x0A]);
+        assert_eq!(probe.issued[1].1, vec![0x09]);
+    }
+}
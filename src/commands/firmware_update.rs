@@ -0,0 +1,221 @@
+//! Self-update subsystem for the WCH-Link probe's own firmware.
+//  COMMAND_ID = 0x0b
+
+use crate::WchLinkVariant;
+
+use super::control::{GetProbeInfo, ProbeInfo};
+use super::probe::Probe;
+use super::*;
+
+/// Size, in bytes, of a single firmware chunk streamed to the bootloader.
+pub const CHUNK_LENGTH: usize = 64;
+
+/// EnterBootloader (0x0b, 0x01)
+#[derive(Debug)]
+pub struct EnterBootloader;
+impl Command for EnterBootloader {
+    type Response = ();
+    const COMMAND_ID: u8 = 0x0b;
+    fn payload(&self) -> Vec<u8> {
+        vec![0x01]
+    }
+}
+
+/// EraseFirmware (0x0b, 0x02)
+#[derive(Debug)]
+pub struct EraseFirmware;
+impl Command for EraseFirmware {
+    type Response = ();
+    const COMMAND_ID: u8 = 0x0b;
+    fn payload(&self) -> Vec<u8> {
+        vec![0x02]
+    }
+}
+
+/// WriteFirmwareChunk (0x0b, 0x03). The bootloader acknowledges each chunk
+/// by echoing back the index it just wrote.
+#[derive(Debug)]
+pub struct WriteFirmwareChunk {
+    pub index: u16,
+    pub data: [u8; CHUNK_LENGTH],
+}
+impl Command for WriteFirmwareChunk {
+    type Response = ChunkAck;
+    const COMMAND_ID: u8 = 0x0b;
+    fn payload(&self) -> Vec<u8> {
+        let mut payload = vec![0x03];
+        payload.extend_from_slice(&self.index.to_be_bytes());
+        payload.extend_from_slice(&self.data);
+        payload
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkAck(pub u16);
+impl Response for ChunkAck {
+    fn from_payload(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 2 {
+            return Err(crate::error::Error::InvalidPayloadLength);
+        }
+        Ok(Self(u16::from_be_bytes(bytes.try_into().unwrap())))
+    }
+}
+
+/// A probe firmware image: the [`WchLinkVariant`] it's built for, the
+/// version it updates the probe to, and the raw image bytes.
+#[derive(Debug, Clone)]
+pub struct FirmwareImage {
+    pub variant: WchLinkVariant,
+    pub version: (u8, u8),
+    pub data: Vec<u8>,
+}
+
+/// Flash `image` onto the attached probe: enter the bootloader, erase,
+/// stream chunks with a per-chunk ack, then re-read [`GetProbeInfo`] to
+/// confirm. Rejects a variant mismatch always, and a downgrade unless
+/// `force` is set.
+pub fn update_firmware<P: Probe>(
+    probe: &mut P,
+    current: &ProbeInfo,
+    image: &FirmwareImage,
+    force: bool,
+) -> Result<ProbeInfo> {
+    if image.variant != current.variant {
+        return Err(crate::error::Error::VariantMismatch {
+            expected: current.variant,
+            actual: image.variant,
+        });
+    }
+    if !force && image.version < current.version() {
+        return Err(crate::error::Error::FirmwareDowngrade {
+            current: current.version(),
+            image: image.version,
+        });
+    }
+
+    probe.send_command(EnterBootloader)?;
+    probe.send_command(EraseFirmware)?;
+    for (index, chunk) in image.data.chunks(CHUNK_LENGTH).enumerate() {
+        let mut data = [0u8; CHUNK_LENGTH];
+        data[..chunk.len()].copy_from_slice(chunk);
+        let ack = probe.send_command(WriteFirmwareChunk {
+            index: index as u16,
+            data,
+        })?;
+        if ack.0 != index as u16 {
+            return Err(crate::error::Error::FirmwareChunkAck {
+                expected: index as u16,
+                actual: ack.0,
+            });
+        }
+    }
+
+    let updated = probe.send_command(GetProbeInfo)?;
+    if updated.version() != image.version {
+        return Err(crate::error::Error::FirmwareVerifyFailed {
+            expected: image.version,
+            actual: updated.version(),
+        });
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::probe::MockProbe;
+
+    fn probe_info(variant: WchLinkVariant, version: (u8, u8)) -> ProbeInfo {
+        ProbeInfo {
+            major_version: version.0,
+            minor_version: version.1,
+            variant,
+        }
+    }
+
+    #[test]
+    fn rejects_variant_mismatch_before_touching_the_probe() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        let current = probe_info(variant, (1, 0));
+        let image = FirmwareImage {
+            variant: WchLinkVariant::Ch549,
+            version: (2, 0),
+            data: vec![0u8; CHUNK_LENGTH],
+        };
+        assert!(update_firmware(&mut probe, &current, &image, false).is_err());
+        assert!(probe.issued.is_empty());
+    }
+
+    #[test]
+    fn rejects_downgrade_unless_forced() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        let current = probe_info(variant, (2, 0));
+        let image = FirmwareImage {
+            variant,
+            version: (1, 9),
+            data: vec![0u8; CHUNK_LENGTH],
+        };
+        assert!(update_firmware(&mut probe, &current, &image, false).is_err());
+        assert!(probe.issued.is_empty());
+    }
+
+    #[test]
+    fn allows_same_version_reflash_without_force() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        let current = probe_info(variant, (1, 0));
+        let image = FirmwareImage {
+            variant,
+            version: (1, 0),
+            data: vec![0xAAu8; CHUNK_LENGTH],
+        };
+        probe.push_response(vec![]); // EnterBootloader
+        probe.push_response(vec![]); // EraseFirmware
+        probe.push_response(vec![0x00, 0x00]); // chunk 0 ack
+        probe.push_response(vec![1, 0, 0x01, 0x00]); // GetProbeInfo
+
+        update_firmware(&mut probe, &current, &image, false).unwrap();
+    }
+
+    #[test]
+    fn streams_chunks_and_verifies_the_rebooted_version() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        let current = probe_info(variant, (1, 0));
+        let image = FirmwareImage {
+            variant,
+            version: (1, 1),
+            data: vec![0xAAu8; CHUNK_LENGTH * 2],
+        };
+
+        probe.push_response(vec![]); // EnterBootloader
+        probe.push_response(vec![]); // EraseFirmware
+        probe.push_response(vec![0x00, 0x00]); // chunk 0 ack
+        probe.push_response(vec![0x00, 0x01]); // chunk 1 ack
+        probe.push_response(vec![1, 1, 0x01, 0x00]); // GetProbeInfo
+
+        let updated = update_firmware(&mut probe, &current, &image, false).unwrap();
+        assert_eq!(updated.version(), (1, 1));
+        assert_eq!(probe.issued.len(), 5);
+    }
+
+    #[test]
+    fn rejects_a_mismatched_chunk_ack() {
+        let mut probe = MockProbe::new();
+        let variant = WchLinkVariant::try_from_u8(0x01).unwrap();
+        let current = probe_info(variant, (1, 0));
+        let image = FirmwareImage {
+            variant,
+            version: (1, 1),
+            data: vec![0xAAu8; CHUNK_LENGTH],
+        };
+
+        probe.push_response(vec![]); // EnterBootloader
+        probe.push_response(vec![]); // EraseFirmware
+        probe.push_response(vec![0x00, 0x01]); // wrong chunk ack
+
+        assert!(update_firmware(&mut probe, &current, &image, false).is_err());
+    }
+}
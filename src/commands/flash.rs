@@ -0,0 +1,236 @@
+//! Block-oriented flash read/write/verify traits.
+
+use crate::RiscvChip;
+
+use super::probe::Probe;
+use super::*;
+
+/// Flash page size, in bytes, per [`RiscvChip`] family.
+pub const fn block_length_for(chip: RiscvChip) -> usize {
+    match chip {
+        RiscvChip::CH32V103 => 64,
+        RiscvChip::CH32V203 | RiscvChip::CH32V303 => 256,
+        RiscvChip::CH569 | RiscvChip::CH573 => 256,
+        _ => 64,
+    }
+}
+
+/// Block-aligned read access to a chip's code flash.
+pub trait FlashRead {
+    /// The size, in bytes, of a single transfer. `read` only accepts
+    /// buffers whose length is a multiple of this.
+    const BLOCK_LENGTH: usize;
+
+    /// Read `buf.len()` bytes starting at `addr`, filling `buf` completely.
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<()>;
+}
+
+/// Block-aligned write access to a chip's code flash.
+pub trait FlashWrite {
+    /// The size, in bytes, of a single transfer. `write` rejects any `data`
+    /// whose length isn't a multiple of this.
+    const BLOCK_LENGTH: usize;
+
+    /// Write `data` to `addr`. `data.len()` must be a multiple of
+    /// `BLOCK_LENGTH`, otherwise [`crate::error::Error::BlockLength`] is
+    /// returned.
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<()>;
+}
+
+/// Read back the region just written at `addr` and compare it against
+/// `expected`.
+pub fn verify<T: FlashRead>(target: &mut T, addr: u32, expected: &[u8]) -> Result<bool> {
+    let mut actual = vec![0u8; expected.len()];
+    target.read(addr, &mut actual)?;
+    Ok(actual == expected)
+}
+
+/// Shared length check used by [`FlashWrite`] implementors: `data.len()`
+/// must be a non-zero multiple of `block_length`.
+pub(crate) fn check_block_length(data: &[u8], block_length: usize) -> Result<()> {
+    if data.is_empty() || data.len() % block_length != 0 {
+        return Err(crate::error::Error::BlockLength {
+            length: data.len(),
+            block_length,
+        });
+    }
+    Ok(())
+}
+
+/// ReadFlashBlock (0x02, 0x01): read one `N`-byte page.
+#[derive(Debug)]
+struct ReadFlashBlock<const N: usize> {
+    addr: u32,
+}
+impl<const N: usize> Command for ReadFlashBlock<N> {
+    type Response = FlashBlock<N>;
+    const COMMAND_ID: u8 = 0x02;
+    fn payload(&self) -> Vec<u8> {
+        let mut payload = vec![0x01];
+        payload.extend_from_slice(&self.addr.to_be_bytes());
+        payload.push(N as u8);
+        payload
+    }
+}
+
+/// Response to [`ReadFlashBlock`]; `N` is the page size, so a short or
+/// malformed reply is rejected here instead of panicking on the caller's
+/// side in [`ProbeFlash::read`].
+#[derive(Debug, Clone)]
+struct FlashBlock<const N: usize>(Vec<u8>);
+impl<const N: usize> Response for FlashBlock<N> {
+    fn from_payload(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != N {
+            return Err(crate::error::Error::InvalidPayloadLength);
+        }
+        Ok(Self(bytes.to_vec()))
+    }
+}
+
+/// WriteFlashBlock (0x02, 0x02): write one `data.len()`-sized page.
+#[derive(Debug)]
+struct WriteFlashBlock {
+    addr: u32,
+    data: Vec<u8>,
+}
+impl Command for WriteFlashBlock {
+    type Response = ();
+    const COMMAND_ID: u8 = 0x02;
+    fn payload(&self) -> Vec<u8> {
+        let mut payload = vec![0x02];
+        payload.extend_from_slice(&self.addr.to_be_bytes());
+        payload.extend_from_slice(&self.data);
+        payload
+    }
+}
+
+/// Block-aligned flash access over a [`Probe`], with the page size fixed to
+/// `N` bytes. Use [`probe_flash`] to get one sized for a given chip.
+pub struct ProbeFlash<P, const N: usize> {
+    probe: P,
+}
+
+impl<P: Probe, const N: usize> ProbeFlash<P, N> {
+    fn new(probe: P) -> Self {
+        Self { probe }
+    }
+}
+
+impl<P: Probe, const N: usize> FlashRead for ProbeFlash<P, N> {
+    const BLOCK_LENGTH: usize = N;
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<()> {
+        check_block_length(buf, N)?;
+        for (i, chunk) in buf.chunks_mut(N).enumerate() {
+            let block = self.probe.send_command(ReadFlashBlock::<N> {
+                addr: addr + (i * N) as u32,
+            })?;
+            chunk.copy_from_slice(&block.0[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+impl<P: Probe, const N: usize> FlashWrite for ProbeFlash<P, N> {
+    const BLOCK_LENGTH: usize = N;
+
+    fn write(&mut self, addr: u32, data: &[u8]) -> Result<()> {
+        check_block_length(data, N)?;
+        for (i, chunk) in data.chunks(N).enumerate() {
+            self.probe.send_command(WriteFlashBlock {
+                addr: addr + (i * N) as u32,
+                data: chunk.to_vec(),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`ProbeFlash`] sized for whichever page length [`block_length_for`]
+/// picked for the attached chip.
+pub enum AnyProbeFlash<P> {
+    Page64(ProbeFlash<P, 64>),
+    Page256(ProbeFlash<P, 256>),
+}
+
+/// Build a block-aligned flash accessor sized for `chip`.
+pub fn probe_flash<P: Probe>(probe: P, chip: RiscvChip) -> AnyProbeFlash<P> {
+    match block_length_for(chip) {
+        64 => AnyProbeFlash::Page64(ProbeFlash::new(probe)),
+        256 => AnyProbeFlash::Page256(ProbeFlash::new(probe)),
+        other => unreachable!("block_length_for returned unhandled length {other}"),
+    }
+}
+
+impl<P: Probe> AnyProbeFlash<P> {
+    pub fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<()> {
+        match self {
+            AnyProbeFlash::Page64(f) => f.read(addr, buf),
+            AnyProbeFlash::Page256(f) => f.read(addr, buf),
+        }
+    }
+
+    pub fn write(&mut self, addr: u32, data: &[u8]) -> Result<()> {
+        match self {
+            AnyProbeFlash::Page64(f) => f.write(addr, data),
+            AnyProbeFlash::Page256(f) => f.write(addr, data),
+        }
+    }
+
+    pub fn verify(&mut self, addr: u32, expected: &[u8]) -> Result<bool> {
+        match self {
+            AnyProbeFlash::Page64(f) => verify(f, addr, expected),
+            AnyProbeFlash::Page256(f) => verify(f, addr, expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::probe::MockProbe;
+    use crate::RiscvChip;
+
+    #[test]
+    fn block_length_for_picks_known_families() {
+        assert_eq!(block_length_for(RiscvChip::CH32V103), 64);
+        assert_eq!(block_length_for(RiscvChip::CH32V203), 256);
+    }
+
+    #[test]
+    fn check_block_length_rejects_unaligned_data() {
+        assert!(check_block_length(&[0u8; 3], 4).is_err());
+        assert!(check_block_length(&[], 4).is_err());
+        assert!(check_block_length(&[0u8; 8], 4).is_ok());
+    }
+
+    #[test]
+    fn write_rejects_non_block_aligned_length() {
+        let mut flash = ProbeFlash::<_, 4>::new(MockProbe::new());
+        assert!(flash.write(0, &[0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn read_write_round_trip() {
+        let mut probe = MockProbe::new();
+        probe.push_response(vec![]); // ack for write block 0
+        probe.push_response(vec![]); // ack for write block 1
+        probe.push_response(vec![1, 2, 3, 4]); // read block 0
+        probe.push_response(vec![5, 6, 7, 8]); // read block 1
+        let mut flash = ProbeFlash::<_, 4>::new(probe);
+
+        flash.write(0, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+
+        let mut buf = [0u8; 8];
+        flash.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn verify_detects_mismatch() {
+        let mut probe = MockProbe::new();
+        probe.push_response(vec![0x00; 4]);
+        let mut flash = ProbeFlash::<_, 4>::new(probe);
+        assert!(!verify(&mut flash, 0, &[0x01, 0x01, 0x01, 0x01]).unwrap());
+    }
+}
@@ -0,0 +1,143 @@
+//! Transport abstraction for the probe command layer.
+//!
+//! [`Probe`] is the transport that the commands in
+//! [`control`](super::control) are sent over, as a trait, so flashing logic
+//! can be written once and run either against real hardware or the
+//! in-memory [`MockProbe`] below, without touching a WCH-Link.
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+use super::control::{AttachChip, AttachChipResponse, OptEnd, SetPower};
+use super::*;
+
+/// Something that can issue probe [`Command`]s and drive the probe's
+/// attach/detach/power lifecycle.
+pub trait Probe {
+    /// Send a single command and decode its response.
+    fn send_command<C: Command>(&mut self, cmd: C) -> Result<C::Response>;
+
+    /// Attach to the target chip.
+    fn attach(&mut self) -> Result<AttachChipResponse> {
+        self.send_command(AttachChip)
+    }
+
+    /// Detach from the target chip.
+    fn detach(&mut self) -> Result<()> {
+        self.send_command(OptEnd)
+    }
+
+    /// Enable or disable the 3V3 target power rail.
+    fn set_power(&mut self, enable: bool) -> Result<()> {
+        self.send_command(if enable {
+            SetPower::Enable3V3
+        } else {
+            SetPower::Disable3V3
+        })
+    }
+}
+
+/// Frames a [`Command`] the way the WCH-Link firmware expects it on the wire:
+/// `81 <command id> <payload length> <payload...>`.
+fn frame<C: Command>(cmd: &C) -> Vec<u8> {
+    let payload = cmd.payload();
+    let mut frame = Vec::with_capacity(payload.len() + 3);
+    frame.push(0x81);
+    frame.push(C::COMMAND_ID);
+    frame.push(payload.len() as u8);
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+/// Real USB implementation of [`Probe`].
+///
+/// Generic over the underlying device handle so this module doesn't need to
+/// pull in a particular USB crate: anything that reads and writes the
+/// vendor bulk endpoints works.
+pub struct UsbProbe<D> {
+    device: D,
+}
+
+impl<D> UsbProbe<D>
+where
+    D: Read + Write,
+{
+    pub fn new(device: D) -> Self {
+        Self { device }
+    }
+}
+
+impl<D> Probe for UsbProbe<D>
+where
+    D: Read + Write,
+{
+    fn send_command<C: Command>(&mut self, cmd: C) -> Result<C::Response> {
+        self.device
+            .write_all(&frame(&cmd))
+            .map_err(|_| crate::error::Error::UsbTransport)?;
+        // Response framing mirrors the request: a leading marker byte, the
+        // echoed command id, a length byte, then the payload.
+        let mut header = [0u8; 3];
+        self.device
+            .read_exact(&mut header)
+            .map_err(|_| crate::error::Error::UsbTransport)?;
+        let mut payload = vec![0u8; header[2] as usize];
+        self.device
+            .read_exact(&mut payload)
+            .map_err(|_| crate::error::Error::UsbTransport)?;
+        C::Response::from_payload(&payload)
+    }
+}
+
+/// In-memory [`Probe`] for unit tests: records the `(command id, payload)`
+/// of every command it was asked to send, and replays scripted response
+/// payloads in the order they were pushed.
+#[derive(Debug, Default)]
+pub struct MockProbe {
+    pub issued: Vec<(u8, Vec<u8>)>,
+    scripted: VecDeque<Vec<u8>>,
+}
+
+impl MockProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the raw response payload for the next command sent.
+    pub fn push_response(&mut self, payload: Vec<u8>) {
+        self.scripted.push_back(payload);
+    }
+}
+
+impl Probe for MockProbe {
+    fn send_command<C: Command>(&mut self, cmd: C) -> Result<C::Response> {
+        self.issued.push((C::COMMAND_ID, cmd.payload()));
+        let payload = self
+            .scripted
+            .pop_front()
+            .ok_or(crate::error::Error::InvalidPayloadLength)?;
+        C::Response::from_payload(&payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RiscvChip;
+
+    #[test]
+    fn attach_then_set_power_round_trip() {
+        let mut probe = MockProbe::new();
+        probe.push_response(vec![RiscvChip::CH32V203 as u8, 0x00, 0x00, 0x00, 0x01]);
+        probe.push_response(vec![]);
+
+        let attached = probe.attach().unwrap();
+        assert_eq!(attached.chip_family, RiscvChip::CH32V203);
+        assert_eq!(attached.chip_id, 1);
+
+        probe.set_power(true).unwrap();
+
+        assert_eq!(probe.issued.len(), 2);
+        assert_eq!(probe.issued[1].1, vec![0x09]);
+    }
+}
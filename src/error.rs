@@ -0,0 +1,82 @@
+//! Crate error type.
+
+use std::fmt;
+
+use crate::WchLinkVariant;
+
+/// Crate-wide result alias.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by the command and probe layers.
+#[derive(Debug)]
+pub enum Error {
+    /// A response's payload didn't have the length a command expects.
+    InvalidPayloadLength,
+    /// A USB transfer to or from the probe failed.
+    UsbTransport,
+    /// A flash read or write was given a length that isn't a non-zero
+    /// multiple of the chip's block length.
+    BlockLength { length: usize, block_length: usize },
+    /// The connected probe variant doesn't support this command.
+    UnsupportedVariant(WchLinkVariant),
+    /// The connected probe's firmware predates this command.
+    UnsupportedFirmware { required: (u8, u8), actual: (u8, u8) },
+    /// A firmware image targets a different probe variant than the one
+    /// attached.
+    VariantMismatch {
+        expected: WchLinkVariant,
+        actual: WchLinkVariant,
+    },
+    /// A firmware image is older than the version already running, and the
+    /// update wasn't forced.
+    FirmwareDowngrade { current: (u8, u8), image: (u8, u8) },
+    /// The bootloader acknowledged a different chunk index than the one
+    /// just sent.
+    FirmwareChunkAck { expected: u16, actual: u16 },
+    /// The probe's version after rebooting doesn't match the image that
+    /// was just flashed.
+    FirmwareVerifyFailed { expected: (u8, u8), actual: (u8, u8) },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidPayloadLength => write!(f, "invalid payload length"),
+            Error::UsbTransport => write!(f, "USB transport error"),
+            Error::BlockLength {
+                length,
+                block_length,
+            } => write!(
+                f,
+                "data length {length} is not a multiple of the block length {block_length}"
+            ),
+            Error::UnsupportedVariant(variant) => {
+                write!(f, "{variant} does not support this command")
+            }
+            Error::UnsupportedFirmware { required, actual } => write!(
+                f,
+                "firmware v{}.{} is too old, v{}.{} or newer required",
+                actual.0, actual.1, required.0, required.1
+            ),
+            Error::VariantMismatch { expected, actual } => {
+                write!(f, "firmware image targets {actual} but probe is {expected}")
+            }
+            Error::FirmwareDowngrade { current, image } => write!(
+                f,
+                "refusing to downgrade firmware from v{}.{} to v{}.{} without force",
+                current.0, current.1, image.0, image.1
+            ),
+            Error::FirmwareChunkAck { expected, actual } => write!(
+                f,
+                "bootloader acknowledged chunk {actual}, expected {expected}"
+            ),
+            Error::FirmwareVerifyFailed { expected, actual } => write!(
+                f,
+                "probe reports v{}.{} after update, expected v{}.{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}